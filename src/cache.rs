@@ -0,0 +1,91 @@
+use crate::error::Error;
+use crate::source_desc::SourceFileDesc;
+use filetime::FileTime;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Memoizes the result of parsing a single file, keyed by `(path, mtime)`,
+/// so rescanning a mostly-unchanged tree doesn't re-run `syn` on every file
+/// every time.
+///
+/// Caching is per file, not per tree: a cache *hit* for one file only skips
+/// that file's own parse -- its child `mod`/`include!` descriptors still get
+/// walked fresh by the normal queue in [`crate::mod_srcfiles`], since those
+/// children may have changed even if this file didn't.
+#[derive(Debug, Default)]
+pub struct SrcfilesCache {
+    entries: HashMap<PathBuf, (FileTime, Vec<SourceFileDesc>, Vec<Error>)>,
+}
+
+impl SrcfilesCache {
+    /// An empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up a cached parse of `path`, if its mtime still matches what we
+    /// last saw.
+    pub(crate) fn get(&self, path: &Path, mtime: FileTime) -> Option<(Vec<SourceFileDesc>, Vec<Error>)> {
+        self.entries
+            .get(path)
+            .filter(|(cached_mtime, ..)| *cached_mtime == mtime)
+            .map(|(_, sources, errors)| (sources.clone(), errors.clone()))
+    }
+
+    /// Record a freshly parsed result for `path` at `mtime`.
+    pub(crate) fn insert(
+        &mut self,
+        path: PathBuf,
+        mtime: FileTime,
+        sources: Vec<SourceFileDesc>,
+        errors: Vec<Error>,
+    ) {
+        self.entries.insert(path, (mtime, sources, errors));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_desc::{ModType, SourceFileType};
+
+    fn desc(path: &str) -> SourceFileDesc {
+        SourceFileDesc::new(
+            PathBuf::from(path),
+            SourceFileType::RustSource(ModType::Adjacent),
+            None,
+        )
+    }
+
+    #[test]
+    fn miss_on_unknown_path() {
+        let cache = SrcfilesCache::new();
+        assert!(cache.get(Path::new("a.rs"), FileTime::from_unix_time(1, 0)).is_none());
+    }
+
+    #[test]
+    fn hit_when_mtime_matches() {
+        let mut cache = SrcfilesCache::new();
+        let mtime = FileTime::from_unix_time(100, 0);
+        cache.insert(PathBuf::from("a.rs"), mtime, vec![desc("a.rs")], vec![]);
+
+        let (sources, errors) = cache.get(Path::new("a.rs"), mtime).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].path, PathBuf::from("a.rs"));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn miss_when_mtime_changed() {
+        let mut cache = SrcfilesCache::new();
+        cache.insert(
+            PathBuf::from("a.rs"),
+            FileTime::from_unix_time(100, 0),
+            vec![desc("a.rs")],
+            vec![],
+        );
+
+        let bumped = FileTime::from_unix_time(200, 0);
+        assert!(cache.get(Path::new("a.rs"), bumped).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! Static evaluation of `#[cfg(...)]` predicates and `cfg_if!` branches.
+//!
+//! Because this crate never actually compiles the code it's scanning, it has
+//! no way to know the *real* value of a cfg atom like `target_os` or
+//! `feature`. [`CfgSet`] lets a caller tell it what it does know; everything
+//! else evaluates to [`CfgValue::Unknown`], so a `#[cfg]`-guarded module or
+//! `include!` is only ever dropped when its predicate can be *proven* false.
+//! `Unknown` and `True` both keep it. `cfg_if!` is the exception: as in the
+//! macro itself, only the first branch whose predicate isn't proven false is
+//! kept, and the branches after it are dropped even when their own predicate
+//! is `Unknown`.
+
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Expr, Lit, Meta};
+
+/// A three-valued result of evaluating a `cfg` predicate against a
+/// [`CfgSet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CfgValue {
+    True,
+    False,
+    /// The caller didn't say, so this can't be proven either way.
+    Unknown,
+}
+
+impl CfgValue {
+    fn not(self) -> CfgValue {
+        match self {
+            CfgValue::True => CfgValue::False,
+            CfgValue::False => CfgValue::True,
+            CfgValue::Unknown => CfgValue::Unknown,
+        }
+    }
+
+    fn and(self, other: CfgValue) -> CfgValue {
+        match (self, other) {
+            (CfgValue::False, _) | (_, CfgValue::False) => CfgValue::False,
+            (CfgValue::True, CfgValue::True) => CfgValue::True,
+            _ => CfgValue::Unknown,
+        }
+    }
+
+    fn or(self, other: CfgValue) -> CfgValue {
+        match (self, other) {
+            (CfgValue::True, _) | (_, CfgValue::True) => CfgValue::True,
+            (CfgValue::False, CfgValue::False) => CfgValue::False,
+            _ => CfgValue::Unknown,
+        }
+    }
+
+    /// Whether a module guarded by this predicate should be dropped. Only a
+    /// predicate proven `False` is droppable; `True` and `Unknown` are both
+    /// kept.
+    pub(crate) fn is_definitely_false(self) -> bool {
+        self == CfgValue::False
+    }
+}
+
+/// The set of cfg atoms and key/value pairs a caller knows to be set, used to
+/// evaluate `#[cfg(...)]` predicates found while scanning.
+///
+/// Anything not mentioned here is [`CfgValue::Unknown`], not `False` -- an
+/// empty `CfgSet` (the default) therefore evaluates every atom and key/value
+/// leaf to `Unknown`, which keeps every `#[cfg]`-guarded module and
+/// `include!`. It still takes only the first branch of each `cfg_if!`; only
+/// scanning with no `CfgSet` at all keeps every branch.
+#[derive(Debug, Clone, Default)]
+pub struct CfgSet {
+    atoms: HashSet<String>,
+    values: HashMap<String, HashSet<String>>,
+}
+
+impl CfgSet {
+    /// An empty set: every predicate evaluates to [`CfgValue::Unknown`].
+    pub fn new() -> Self {
+        CfgSet::default()
+    }
+
+    /// Mark a bare atom (e.g. `unix`, `test`) as set.
+    pub fn set_atom(&mut self, atom: impl Into<String>) -> &mut Self {
+        self.atoms.insert(atom.into());
+        self
+    }
+
+    /// Mark a key/value pair (e.g. `target_os = "linux"`, `feature = "foo"`)
+    /// as set. A key may have more than one value set at once, matching how
+    /// `feature = "a"` and `feature = "b"` can both be true simultaneously.
+    pub fn set_value(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.values.entry(key.into()).or_default().insert(value.into());
+        self
+    }
+
+    fn has_atom(&self, atom: &str) -> bool {
+        self.atoms.contains(atom)
+    }
+
+    fn has_value(&self, key: &str, value: &str) -> bool {
+        self.values.get(key).is_some_and(|vs| vs.contains(value))
+    }
+
+    /// Evaluate a single `#[cfg(...)]` attribute's predicate.
+    pub(crate) fn eval_attr(&self, attr: &syn::Attribute) -> CfgValue {
+        match &attr.meta {
+            Meta::List(list) => match list.parse_args::<Meta>() {
+                Ok(meta) => self.eval(&meta),
+                Err(_) => CfgValue::Unknown,
+            },
+            _ => CfgValue::Unknown,
+        }
+    }
+
+    /// Evaluate a `cfg(...)` meta tree: a leaf is membership in the set,
+    /// `all`/`any`/`not` combine their children with the usual boolean
+    /// meaning, lifted to three values.
+    pub(crate) fn eval(&self, meta: &Meta) -> CfgValue {
+        match meta {
+            Meta::Path(path) => match path_ident(path) {
+                Some(atom) if self.has_atom(&atom) => CfgValue::True,
+                Some(_) => CfgValue::Unknown,
+                None => CfgValue::Unknown,
+            },
+            Meta::NameValue(nv) => match (path_ident(&nv.path), expr_str(&nv.value)) {
+                (Some(key), Some(value)) if self.has_value(&key, &value) => CfgValue::True,
+                (Some(_), Some(_)) => CfgValue::Unknown,
+                _ => CfgValue::Unknown,
+            },
+            Meta::List(list) => {
+                let op = path_ident(&list.path).unwrap_or_default();
+                let nested = match list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated) {
+                    Ok(nested) => nested,
+                    Err(_) => return CfgValue::Unknown,
+                };
+                match op.as_str() {
+                    "not" => nested
+                        .first()
+                        .map(|m| self.eval(m).not())
+                        .unwrap_or(CfgValue::Unknown),
+                    "all" => nested.iter().map(|m| self.eval(m)).fold(CfgValue::True, CfgValue::and),
+                    "any" => nested.iter().map(|m| self.eval(m)).fold(CfgValue::False, CfgValue::or),
+                    _ => CfgValue::Unknown,
+                }
+            }
+        }
+    }
+}
+
+fn path_ident(path: &syn::Path) -> Option<String> {
+    path.get_ident().map(|ident| ident.to_string())
+}
+
+fn expr_str(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: [CfgValue; 3] = [CfgValue::True, CfgValue::False, CfgValue::Unknown];
+
+    #[test]
+    fn not_test() {
+        assert_eq!(CfgValue::True.not(), CfgValue::False);
+        assert_eq!(CfgValue::False.not(), CfgValue::True);
+        assert_eq!(CfgValue::Unknown.not(), CfgValue::Unknown);
+    }
+
+    #[test]
+    fn and_test() {
+        for a in VALUES {
+            for b in VALUES {
+                let expected = match (a, b) {
+                    (CfgValue::False, _) | (_, CfgValue::False) => CfgValue::False,
+                    (CfgValue::True, CfgValue::True) => CfgValue::True,
+                    _ => CfgValue::Unknown,
+                };
+                assert_eq!(a.and(b), expected, "{a:?}.and({b:?})");
+            }
+        }
+    }
+
+    #[test]
+    fn or_test() {
+        for a in VALUES {
+            for b in VALUES {
+                let expected = match (a, b) {
+                    (CfgValue::True, _) | (_, CfgValue::True) => CfgValue::True,
+                    (CfgValue::False, CfgValue::False) => CfgValue::False,
+                    _ => CfgValue::Unknown,
+                };
+                assert_eq!(a.or(b), expected, "{a:?}.or({b:?})");
+            }
+        }
+    }
+
+    fn cfg_meta(src: &str) -> Meta {
+        syn::parse_str::<Meta>(src).unwrap()
+    }
+
+    #[test]
+    fn eval_leaf_test() {
+        let mut cfg = CfgSet::new();
+        cfg.set_atom("unix");
+        cfg.set_value("target_os", "linux");
+
+        assert_eq!(cfg.eval(&cfg_meta("unix")), CfgValue::True);
+        assert_eq!(cfg.eval(&cfg_meta("windows")), CfgValue::Unknown);
+        assert_eq!(
+            cfg.eval(&cfg_meta(r#"target_os = "linux""#)),
+            CfgValue::True
+        );
+        assert_eq!(
+            cfg.eval(&cfg_meta(r#"target_os = "macos""#)),
+            CfgValue::Unknown
+        );
+    }
+
+    #[test]
+    fn eval_combinators_test() {
+        let mut cfg = CfgSet::new();
+        cfg.set_atom("unix");
+
+        assert_eq!(cfg.eval(&cfg_meta("not(unix)")), CfgValue::False);
+        assert_eq!(cfg.eval(&cfg_meta("not(windows)")), CfgValue::Unknown);
+        assert_eq!(cfg.eval(&cfg_meta("all(unix, windows)")), CfgValue::Unknown);
+        assert_eq!(cfg.eval(&cfg_meta("all(unix, not(unix))")), CfgValue::False);
+        assert_eq!(cfg.eval(&cfg_meta("any(unix, windows)")), CfgValue::True);
+        assert_eq!(cfg.eval(&cfg_meta("any(windows, linux)")), CfgValue::Unknown);
+    }
+}
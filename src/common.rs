@@ -0,0 +1,10 @@
+//! Small helpers shared by the rest of the crate.
+
+use std::path::{Path, PathBuf};
+
+/// The two file paths Rust will look for a `mod name;` declaration rooted at
+/// `dir`, in the order `rustc` itself tries them: `name.rs`, then
+/// `name/mod.rs`.
+pub(crate) fn mod_candidates(dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+    (dir.join(format!("{}.rs", name)), dir.join(name).join("mod.rs"))
+}
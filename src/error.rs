@@ -0,0 +1,105 @@
+use crate::SourceFileDesc;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Errors encountered while resolving a crate's source files.
+///
+/// The I/O-backed variants are `Arc`-wrapped so `Error` stays cheaply
+/// `Clone`, which [`crate::SrcfilesCache`] relies on to hand out cached
+/// results.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// Failed to read a source file from disk.
+    Io(Arc<std::io::Error>),
+    /// Failed to parse a source file.
+    Syn(Arc<syn::Error>),
+    /// Failed to read or parse a `Cargo.toml` manifest.
+    Manifest(Arc<cargo_toml::Error>),
+    /// A `mod` item referenced a file that does not exist. The targets of
+    /// `include!`, `include_bytes!` and `include_str!` are not checked for
+    /// existence: a missing `include!` target surfaces as [`Error::Io`] when
+    /// it is read, and a missing `include_bytes!`/`include_str!` target is
+    /// not reported.
+    MissingFile(SourceFileDesc),
+    /// An `include!` (or a `#[path]` module) formed a cycle back to one of
+    /// its own ancestor files.
+    CircularInclude {
+        /// The file that would have been included again.
+        path: PathBuf,
+        /// The chain of ancestor files, starting at the root module, that
+        /// led back to `path`.
+        chain: Vec<PathBuf>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Syn(err) => write!(f, "{}", err),
+            Error::Manifest(err) => write!(f, "{}", err),
+            Error::MissingFile(desc) => write!(f, "missing file: {}", desc.path.display()),
+            Error::CircularInclude { path, chain } => write!(
+                f,
+                "circular include: {} already appears in {:?}",
+                path.display(),
+                chain
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(Arc::new(err))
+    }
+}
+
+impl From<syn::Error> for Error {
+    fn from(err: syn::Error) -> Self {
+        Error::Syn(Arc::new(err))
+    }
+}
+
+impl From<cargo_toml::Error> for Error {
+    fn from(err: cargo_toml::Error) -> Self {
+        Error::Manifest(Arc::new(err))
+    }
+}
+
+/// Sources and errors accumulated while walking a crate.
+///
+/// Walking continues past errors so that, even if some module couldn't be
+/// resolved, the caller can still get the list of everything that could be.
+#[derive(Debug)]
+pub struct SourcesAndErrors {
+    pub(crate) sources: Vec<(SourceFileDesc, Vec<Error>)>,
+}
+
+impl SourcesAndErrors {
+    pub(crate) fn new(sources: Vec<(SourceFileDesc, Vec<Error>)>) -> Self {
+        SourcesAndErrors { sources }
+    }
+
+    /// All source files discovered, regardless of whether they had errors.
+    pub fn get_sources(&self) -> Vec<SourceFileDesc> {
+        self.sources.iter().map(|(desc, _)| desc.clone()).collect()
+    }
+
+    /// Consume `self`, returning just the discovered source files.
+    pub fn into_sources(self) -> Vec<SourceFileDesc> {
+        self.sources.into_iter().map(|(desc, _)| desc).collect()
+    }
+
+    /// Consume `self`, returning a `(file, error)` pair for every error that
+    /// was encountered.
+    pub fn into_errors(self) -> Vec<(SourceFileDesc, Error)> {
+        self.sources
+            .into_iter()
+            .flat_map(|(desc, errors)| errors.into_iter().map(move |err| (desc.clone(), err)))
+            .collect()
+    }
+}
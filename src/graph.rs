@@ -0,0 +1,107 @@
+//! Serializable view of the include/mod relationships discovered while
+//! walking a crate, for tools that aren't Rust to consume.
+
+use crate::source_desc::{SourceFileDesc, SourceFileType};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// What kind of item pulled a child file in.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// A `mod` item (inline or file-backed).
+    Mod,
+    /// `include!`.
+    Include,
+    /// `include_bytes!`.
+    IncludeBytes,
+    /// `include_str!`.
+    IncludeStr,
+}
+
+impl EdgeKind {
+    fn of(file_type: &SourceFileType) -> Self {
+        match file_type {
+            SourceFileType::RustSource(_) => EdgeKind::Mod,
+            SourceFileType::RustSnippet(_) => EdgeKind::Include,
+            SourceFileType::Bytes => EdgeKind::IncludeBytes,
+            SourceFileType::String => EdgeKind::IncludeStr,
+        }
+    }
+}
+
+/// One child of a parent file, tagged by how it was pulled in.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct GraphEdge {
+    pub kind: EdgeKind,
+    pub child: SourceFileDesc,
+}
+
+/// The include/mod relationships discovered while walking a crate, as an
+/// adjacency list from parent path to its children.
+///
+/// The root module (the file passed to [`crate::crate_srcfiles`]) has no
+/// parent, so it never appears as anybody's child here -- only as a key, if
+/// it `mod`/`include!`s anything, or not at all if it doesn't.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct SourceGraph {
+    pub edges: HashMap<PathBuf, Vec<GraphEdge>>,
+}
+
+/// Materialize `sources` (as produced by [`crate::crate_srcfiles`] and
+/// friends) into a [`SourceGraph`] keyed by each file's `parent_file`.
+pub fn to_graph(sources: &[SourceFileDesc]) -> SourceGraph {
+    let mut graph = SourceGraph::default();
+    for source in sources {
+        if let Some(parent) = &source.parent_file {
+            graph
+                .edges
+                .entry(parent.clone())
+                .or_default()
+                .push(GraphEdge {
+                    kind: EdgeKind::of(&source.file_type),
+                    child: source.clone(),
+                });
+        }
+    }
+    graph
+}
+
+/// Convenience wrapper around [`to_graph`] that serializes the result as a
+/// JSON string, for non-Rust build orchestrators to ingest.
+#[cfg(feature = "serde")]
+pub fn to_graph_json(sources: &[SourceFileDesc]) -> serde_json::Result<String> {
+    serde_json::to_string(&to_graph(sources))
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::source_desc::ModType;
+
+    #[test]
+    fn to_graph_json_round_trip() {
+        let sources = vec![
+            SourceFileDesc::new(
+                PathBuf::from("src/lib.rs"),
+                SourceFileType::RustSource(ModType::ModRs),
+                None,
+            ),
+            SourceFileDesc::new(
+                PathBuf::from("src/a.rs"),
+                SourceFileType::RustSource(ModType::Adjacent),
+                Some(PathBuf::from("src/lib.rs")),
+            ),
+        ];
+
+        let json = to_graph_json(&sources).unwrap();
+        let graph: SourceGraph = serde_json::from_str(&json).unwrap();
+
+        let edges = graph.edges.get(&PathBuf::from("src/lib.rs")).unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].kind, EdgeKind::Mod);
+        assert_eq!(edges[0].child.path, PathBuf::from("src/a.rs"));
+    }
+}
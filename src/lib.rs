@@ -10,10 +10,18 @@
 //! There are a number of limitations with this:
 //! - Only works on Rust 2018 code (TODO: check 2021)
 //! - Will not file source files which are hidden by a macro or proc-macro
-//! - Will be confused by conditional includes
+//! - By default, conditional includes (`#[cfg(...)]` and `cfg_if!`) are not
+//!   evaluated, so every branch is included. Pass a [`CfgSet`] to
+//!   [`crate_srcfiles_with_cfg`] to prune branches that can be proven
+//!   disabled.
 //!
 //! There is some specific support for the `cfg_if` macro.
 //!
+//! With the `serde` feature enabled, [`SourceFileDesc`] and friends are
+//! `Serialize`/`Deserialize`, and [`to_graph`] turns a resolved source list
+//! into a parent-path-to-children adjacency list for consumption by
+//! non-Rust build orchestrators.
+//!
 //! ## Example
 //! ```rust
 //! let result = srcfiles::crate_srcfiles("test_projects/simple/src/main.rs").unwrap_err();
@@ -22,19 +30,33 @@
 //! assert_eq!(srcfiles.len(), 7);
 //! ```
 
+mod cache;
+mod cfg;
 mod common;
 mod error;
+mod graph;
+mod manifest;
 mod mod_path;
 mod source_desc;
 mod visitor;
 
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs::File;
 use std::io::Read;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use filetime::FileTime;
 use syn::visit::Visit;
 
+pub use cache::SrcfilesCache;
+pub use cfg::CfgSet;
 pub use error::{Error, SourcesAndErrors};
+pub use graph::{to_graph, EdgeKind, GraphEdge, SourceGraph};
+#[cfg(feature = "serde")]
+pub use graph::to_graph_json;
+pub use manifest::manifest_srcfiles;
 pub use mod_path::ModPath;
 pub use source_desc::{ModType, SourceFileDesc, SourceFileType};
 use visitor::SourceFinder;
@@ -45,11 +67,11 @@ fn propagate_parent_file(path: &Path, source_descs_slice: &mut [SourceFileDesc])
     }
 }
 
-fn visit_source(
+fn parse_source(
     path: &Path,
     mut source_finder: SourceFinder,
 ) -> Result<(Vec<SourceFileDesc>, Vec<Error>), Error> {
-    let mut file = File::open(&path)?;
+    let mut file = File::open(path)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
     let ast = syn::parse_file(&content)?;
@@ -64,16 +86,46 @@ fn visit_source(
     ))
 }
 
-fn process_source(source: &SourceFileDesc) -> Result<(Vec<SourceFileDesc>, Vec<Error>), Error> {
+/// Parse `path`, reusing `cache`'s stored result if the file's mtime hasn't
+/// moved since it was last cached.
+///
+/// A hit only skips *this* file's own parse -- its child descriptors still
+/// get walked fresh by the queue in [`mod_srcfiles`], since those may have
+/// changed even when `path` itself hasn't.
+fn visit_source(
+    path: &Path,
+    source_finder: SourceFinder,
+    cache: Option<&mut SrcfilesCache>,
+) -> Result<(Vec<SourceFileDesc>, Vec<Error>), Error> {
+    let cache = match cache {
+        Some(cache) => cache,
+        None => return parse_source(path, source_finder),
+    };
+
+    let mtime = FileTime::from_last_modification_time(&std::fs::metadata(path)?);
+    if let Some(cached) = cache.get(path, mtime) {
+        return Ok(cached);
+    }
+
+    let result = parse_source(path, source_finder)?;
+    cache.insert(path.to_owned(), mtime, result.0.clone(), result.1.clone());
+    Ok(result)
+}
+
+fn process_source(
+    source: &SourceFileDesc,
+    cfg: Option<CfgSet>,
+    cache: Option<&mut SrcfilesCache>,
+) -> Result<(Vec<SourceFileDesc>, Vec<Error>), Error> {
     let source_finder = match &source.file_type {
         SourceFileType::Bytes | SourceFileType::String => return Ok((vec![], vec![])),
-        SourceFileType::RustSnippet(mod_stack) => SourceFinder::new(mod_stack.clone()),
+        SourceFileType::RustSnippet(mod_stack) => SourceFinder::new(mod_stack.clone(), cfg),
         SourceFileType::RustSource(mod_type) => {
-            SourceFinder::from_mod_path(ModPath::new(source.path.clone(), *mod_type))
+            SourceFinder::from_mod_path(ModPath::new(source.path.clone(), *mod_type), cfg)
         }
     };
 
-    Ok(visit_source(&source.path, source_finder)?)
+    visit_source(&source.path, source_finder, cache)
 }
 
 /// Generate list of sources for a crate
@@ -82,34 +134,214 @@ fn process_source(source: &SourceFileDesc) -> Result<(Vec<SourceFileDesc>, Vec<E
 /// of source files (including the top-level module). If there are any errors it
 /// returns `Err`, but the caller can still extract any successfully determined
 /// files from this. If there are no errors it returns `Ok`.
+///
+/// `#[cfg(...)]` and `cfg_if!` branches are not evaluated -- every module is
+/// included regardless of what would actually get compiled. Use
+/// [`crate_srcfiles_with_cfg`] to prune branches that can be proven disabled.
 pub fn crate_srcfiles<P: Into<PathBuf>>(path: P) -> Result<Vec<SourceFileDesc>, SourcesAndErrors> {
+    crate_srcfiles_with_cfg(path, None)
+}
+
+/// Same as [`crate_srcfiles`], but evaluates `#[cfg(...)]` on `mod` items and
+/// `include!`, along with `cfg_if!` branches, against `cfg`.
+///
+/// A `#[cfg]`-guarded module or `include!` is only dropped when its predicate
+/// can be proven false against `cfg`; anything `cfg` doesn't mention
+/// evaluates to "unknown" and is kept. A `cfg_if!` keeps only its first
+/// branch whose predicate isn't proven false, so the branches after it are
+/// dropped even when their own predicate is unknown. Passing `None`
+/// preserves [`crate_srcfiles`]'s "include everything" behavior.
+pub fn crate_srcfiles_with_cfg<P: Into<PathBuf>>(
+    path: P,
+    cfg: Option<CfgSet>,
+) -> Result<Vec<SourceFileDesc>, SourcesAndErrors> {
+    let path = path.into();
+    mod_srcfiles(ModPath::new(path, ModType::ModRs), cfg, None)
+}
+
+/// Same as [`crate_srcfiles`], but memoizes each file's parse in `cache` so a
+/// later rescan can skip re-parsing files whose mtime hasn't changed since
+/// the last call.
+pub fn crate_srcfiles_cached<P: Into<PathBuf>>(
+    path: P,
+    cache: &mut SrcfilesCache,
+) -> Result<Vec<SourceFileDesc>, SourcesAndErrors> {
     let path = path.into();
-    mod_srcfiles(ModPath::new(path, ModType::ModRs))
+    mod_srcfiles(ModPath::new(path, ModType::ModRs), None, Some(cache))
 }
 
-fn mod_srcfiles(mod_path: ModPath) -> Result<Vec<SourceFileDesc>, SourcesAndErrors> {
-    let mut source_queue = Vec::with_capacity(100);
+fn canonical_or_as_is(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Merge every entry whose canonical path we've already kept into that
+/// earlier entry, so a file reachable via two different `mod`/`include!`
+/// routes only appears once.
+///
+/// Errors are merged rather than dropped: two routes to the same file can
+/// have different ancestor chains, so one route may detect a
+/// `CircularInclude` that the other, independently reached, route does not.
+/// Keeping only the first-seen entry's errors would silently lose that
+/// cycle whenever the non-circular route happened to be queued first.
+fn dedupe_by_canonical_path(sources: &mut Vec<(SourceFileDesc, Vec<Error>)>) {
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    let mut deduped: Vec<(SourceFileDesc, Vec<Error>)> = Vec::with_capacity(sources.len());
+    for (desc, errors) in sources.drain(..) {
+        match seen.get(&canonical_or_as_is(&desc.path)) {
+            Some(&index) => deduped[index].1.extend(errors),
+            None => {
+                seen.insert(canonical_or_as_is(&desc.path), deduped.len());
+                deduped.push((desc, errors));
+            }
+        }
+    }
+    *sources = deduped;
+}
+
+pub(crate) fn mod_srcfiles(
+    mod_path: ModPath,
+    cfg: Option<CfgSet>,
+    mut cache: Option<&mut SrcfilesCache>,
+) -> Result<Vec<SourceFileDesc>, SourcesAndErrors> {
+    // Each queued item carries the canonicalized path of every ancestor that
+    // led to it (root first), so a cycle -- a self-referential `include!`, or
+    // a `#[path]` mod pointing back at one of its own ancestors -- can be
+    // caught before we queue the same file forever.
+    let mut source_queue: Vec<(SourceFileDesc, Vec<PathBuf>)> = Vec::with_capacity(100);
     let mut result = SourcesAndErrors::new(vec![]);
 
-    source_queue.push(SourceFileDesc::new(
-        mod_path.path,
-        SourceFileType::RustSource(mod_path.mod_type),
-        None,
+    source_queue.push((
+        SourceFileDesc::new(mod_path.path, SourceFileType::RustSource(mod_path.mod_type), None),
+        vec![],
     ));
 
-    while let Some(source) = source_queue.pop() {
-        match process_source(&source) {
-            Ok((sources, src_errors)) => {
-                source_queue.extend(sources);
+    while let Some((source, ancestors)) = source_queue.pop() {
+        let mut chain = ancestors;
+        chain.push(canonical_or_as_is(&source.path));
+
+        match process_source(&source, cfg.clone(), cache.as_deref_mut()) {
+            Ok((children, mut src_errors)) => {
+                for child in children {
+                    if chain.contains(&canonical_or_as_is(&child.path)) {
+                        src_errors.push(Error::CircularInclude {
+                            path: child.path,
+                            chain: chain.clone(),
+                        });
+                    } else {
+                        source_queue.push((child, chain.clone()));
+                    }
+                }
                 result.sources.push((source, src_errors));
             }
             Err(error) => result.sources.push((source, vec![error])),
         }
     }
 
+    dedupe_by_canonical_path(&mut result.sources);
+
     if result.sources.iter().all(|x| x.1.is_empty()) {
         Ok(result.into_sources())
     } else {
         Err(result)
     }
 }
+
+/// Escape a path for use as a prerequisite or target in a Make `.d` file.
+///
+/// Make splits rule text on whitespace, and treats `#` and `$` specially, so
+/// each of those (plus a literal backslash, which would otherwise be read as
+/// a line continuation) has to be escaped with a leading backslash (`$` is
+/// doubled instead, per Make's own convention).
+fn escape_make_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '$' => escaped.push_str("$$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a GNU Make dep-info file describing `target`'s dependency on
+/// `sources`.
+///
+/// This produces a single rule making `target` depend on every source's
+/// [`SourceFileDesc::path`], plus a trailing empty rule for each dependency
+/// (`dep:` with no recipe or prerequisites) so deleting or renaming a
+/// dependency doesn't leave Make (or ninja) with a rule referencing a
+/// missing file.
+pub fn write_depfile(
+    target: &str,
+    sources: &[SourceFileDesc],
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    write!(out, "{}:", escape_make_path(Path::new(target)))?;
+    for source in sources {
+        write!(out, " {}", escape_make_path(&source.path))?;
+    }
+    writeln!(out)?;
+    for source in sources {
+        writeln!(out, "{}:", escape_make_path(&source.path))?;
+    }
+    Ok(())
+}
+
+/// Builds the same depfile contents as [`write_depfile`], returned as a
+/// `String` instead of written to a sink.
+pub fn depfile_string(target: &str, sources: &[SourceFileDesc]) -> String {
+    let mut out = String::new();
+    write!(out, "{}:", escape_make_path(Path::new(target))).unwrap();
+    for source in sources {
+        write!(out, " {}", escape_make_path(&source.path)).unwrap();
+    }
+    out.push('\n');
+    for source in sources {
+        writeln!(out, "{}:", escape_make_path(&source.path)).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_make_path_test() {
+        assert_eq!(escape_make_path(Path::new("plain.rs")), "plain.rs");
+        assert_eq!(
+            escape_make_path(Path::new("has space.rs")),
+            "has\\ space.rs"
+        );
+        assert_eq!(escape_make_path(Path::new("a#b.rs")), "a\\#b.rs");
+        assert_eq!(escape_make_path(Path::new("a$b.rs")), "a$$b.rs");
+        assert_eq!(escape_make_path(Path::new("a\\b.rs")), "a\\\\b.rs");
+    }
+
+    #[test]
+    fn depfile_string_test() {
+        let sources = vec![
+            SourceFileDesc::new(
+                PathBuf::from("src/lib.rs"),
+                SourceFileType::RustSource(ModType::ModRs),
+                None,
+            ),
+            SourceFileDesc::new(
+                PathBuf::from("src/has space.rs"),
+                SourceFileType::RustSource(ModType::Adjacent),
+                Some(PathBuf::from("src/lib.rs")),
+            ),
+        ];
+        let depfile = depfile_string("target/out.o", &sources);
+        assert_eq!(
+            depfile,
+            "target/out.o: src/lib.rs src/has\\ space.rs\n\
+             src/lib.rs:\n\
+             src/has\\ space.rs:\n"
+        );
+    }
+}
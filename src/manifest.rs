@@ -0,0 +1,105 @@
+//! Enumerate every compilation target of a Cargo package and resolve each
+//! target's own source list.
+
+use crate::{mod_srcfiles, Error, ModPath, ModType, SourceFileDesc};
+use cargo_toml::{Edition, Manifest};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cargo only autodiscovers `[[bin]]`/`[[example]]`/`[[test]]`/`[[bench]]`
+/// targets by default for edition 2018+ packages; a 2015 package has to opt
+/// in with `autobins = true` (and friends) in `Cargo.toml`. `cargo_toml`
+/// itself doesn't apply that edition check (its `auto*` fields just default
+/// to `true`), so replicate it here before asking it to autodiscover paths.
+fn disable_autodiscovery_for_2015(manifest: &mut Manifest) {
+    if let Some(package) = &mut manifest.package {
+        if package.edition() == Edition::E2015 {
+            package.autobins = false;
+            package.autoexamples = false;
+            package.autotests = false;
+            package.autobenches = false;
+        }
+    }
+}
+
+fn resolve_target(
+    manifest_dir: &Path,
+    path: &str,
+    results: &mut HashMap<String, Vec<SourceFileDesc>>,
+    name: String,
+) {
+    let mod_path = ModPath::new(manifest_dir.join(path), ModType::ModRs);
+    let sources = match mod_srcfiles(mod_path, None, None) {
+        Ok(sources) => sources,
+        Err(sources_and_errors) => sources_and_errors.into_sources(),
+    };
+    results.entry(name).or_default().extend(sources);
+}
+
+/// Resolve every compilation target declared (or autodiscovered) by the
+/// `Cargo.toml` in `manifest_dir`: the `[lib]`, every `[[bin]]`,
+/// `[[example]]`, `[[test]]` and `[[bench]]`, and `build.rs` if present.
+///
+/// Each target's module tree is walked independently by seeding
+/// [`mod_srcfiles`] from its entry point, so a target's source list is
+/// deduplicated but two *different* targets that happen to share a source
+/// file (e.g. one `#[path]`-included by both the `[lib]` and a `[[bin]]`)
+/// each get their own entry.
+pub fn manifest_srcfiles<P: Into<PathBuf>>(
+    manifest_dir: P,
+) -> Result<HashMap<String, Vec<SourceFileDesc>>, Error> {
+    let manifest_dir = manifest_dir.into();
+    let manifest_path = manifest_dir.join("Cargo.toml");
+
+    // `Manifest::from_path` runs autodiscovery itself (via its own internal
+    // `complete_from_path` call) before we'd get a chance to apply the 2015
+    // edition gate, so parse without completing first and complete exactly
+    // once, after the `auto*` flags are right.
+    let contents = fs::read_to_string(&manifest_path)?;
+    let mut manifest = Manifest::from_str(&contents)?;
+    disable_autodiscovery_for_2015(&mut manifest);
+    manifest.complete_from_path(&manifest_path)?;
+
+    let mut results = HashMap::new();
+
+    // Cargo lets a `[lib]` and its default `[[bin]]` share the package name
+    // (that's how `cargo new` projects work), so every lib/bin/example/test/
+    // bench key is suffixed by target kind to keep them from colliding in
+    // the map. The build script's key is plain `build.rs`.
+    if let Some(lib) = &manifest.lib {
+        if let Some(path) = &lib.path {
+            let name = lib.name.clone().unwrap_or_else(|| {
+                manifest
+                    .package
+                    .as_ref()
+                    .map(|package| package.name.clone())
+                    .unwrap_or_else(|| "lib".to_owned())
+            });
+            resolve_target(&manifest_dir, path, &mut results, format!("{name} (lib)"));
+        }
+    }
+
+    for (products, kind) in [
+        (&manifest.bin, "bin"),
+        (&manifest.example, "example"),
+        (&manifest.test, "test"),
+        (&manifest.bench, "bench"),
+    ] {
+        for product in products {
+            let (Some(path), Some(name)) = (&product.path, &product.name) else {
+                continue;
+            };
+            resolve_target(&manifest_dir, path, &mut results, format!("{name} ({kind})"));
+        }
+    }
+
+    if let Some(package) = &manifest.package {
+        if let Some(build) = package.build.as_ref().and_then(|build| build.as_path()) {
+            let build = build.to_string_lossy().into_owned();
+            resolve_target(&manifest_dir, &build, &mut results, "build.rs".to_owned());
+        }
+    }
+
+    Ok(results)
+}
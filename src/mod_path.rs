@@ -0,0 +1,43 @@
+use crate::ModType;
+use std::path::{Path, PathBuf};
+
+/// Directory that further `mod`/`include!` items should resolve against.
+///
+/// For code pulled in via `include!`, this is all a [`crate::visitor::SourceFinder`]
+/// needs to carry forward: the included snippet has no file identity of its
+/// own, just a location on disk its own nested items are relative to.
+pub type ModStack = PathBuf;
+
+/// A module, anchored to the file on disk that defines it.
+#[derive(Debug, Clone)]
+pub struct ModPath {
+    pub path: PathBuf,
+    pub mod_type: ModType,
+}
+
+impl ModPath {
+    pub fn new(path: PathBuf, mod_type: ModType) -> Self {
+        ModPath { path, mod_type }
+    }
+
+    /// Directory that this module's own child `mod` items resolve against.
+    ///
+    /// `foo/bar.rs` resolves children under `foo/bar/`, while `foo/bar/mod.rs`
+    /// resolves them alongside itself, under `foo/bar/`.
+    pub(crate) fn child_dir(&self) -> ModStack {
+        match self.mod_type {
+            ModType::ModRs => self
+                .path
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_path_buf(),
+            ModType::Adjacent => {
+                let stem = self.path.file_stem().unwrap_or_default();
+                self.path
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join(stem)
+            }
+        }
+    }
+}
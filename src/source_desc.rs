@@ -2,6 +2,7 @@ use crate::mod_path::ModStack;
 use std::path::PathBuf;
 
 /// Type of module paths
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy)]
 pub enum ModType {
     /// Module named "modname.rs"
@@ -11,6 +12,7 @@ pub enum ModType {
 }
 
 /// Type of source file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub enum SourceFileType {
     /// Rust source module.
@@ -23,6 +25,7 @@ pub enum SourceFileType {
     String,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct SourceFileDesc {
     pub path: PathBuf,
@@ -0,0 +1,300 @@
+use crate::cfg::CfgSet;
+use crate::common::mod_candidates;
+use crate::error::Error;
+use crate::mod_path::{ModPath, ModStack};
+use crate::source_desc::{ModType, SourceFileDesc, SourceFileType};
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Item, ItemMod, Meta};
+
+/// Walks a single parsed file looking for `mod`, `include!`, `include_bytes!`,
+/// `include_str!` and `cfg_if!` items, resolving each to a [`SourceFileDesc`].
+pub(crate) struct SourceFinder {
+    /// Directory that a file-backed child `mod` item resolves against.
+    dir: ModStack,
+    /// What the caller knows about the target `cfg`, if anything. `None`
+    /// means "include everything", matching this crate's default behavior.
+    cfg: Option<CfgSet>,
+    pub(crate) source_candidates: Vec<SourceFileDesc>,
+    pub(crate) unresolved_items: Vec<Error>,
+}
+
+impl SourceFinder {
+    /// Build a finder for code pulled in via `include!`, rooted at `dir` --
+    /// the directory further `mod`/`include!` items inside it resolve
+    /// against.
+    pub(crate) fn new(dir: ModStack, cfg: Option<CfgSet>) -> Self {
+        SourceFinder {
+            dir,
+            cfg,
+            source_candidates: vec![],
+            unresolved_items: vec![],
+        }
+    }
+
+    /// Build a finder for a file-backed module.
+    pub(crate) fn from_mod_path(mod_path: ModPath, cfg: Option<CfgSet>) -> Self {
+        SourceFinder::new(mod_path.child_dir(), cfg)
+    }
+
+    fn child(&self, dir: ModStack) -> SourceFinder {
+        SourceFinder::new(dir, self.cfg.clone())
+    }
+
+    /// Whether `attrs` carries a `#[cfg(...)]` that can be *proven* false.
+    /// With no `CfgSet` (or an attribute we can't parse), nothing is ever
+    /// provably false, so everything is kept.
+    fn is_definitely_disabled(&self, attrs: &[Attribute]) -> bool {
+        let cfg = match &self.cfg {
+            Some(cfg) => cfg,
+            None => return false,
+        };
+        attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .any(|attr| cfg.eval_attr(attr).is_definitely_false())
+    }
+
+    fn push_missing(&mut self, path: PathBuf) {
+        self.unresolved_items
+            .push(Error::MissingFile(SourceFileDesc::new(
+                path,
+                SourceFileType::RustSource(ModType::Adjacent),
+                None,
+            )));
+    }
+
+    fn resolve_mod(&mut self, name: &str, path_attr: Option<PathBuf>) {
+        if let Some(rel) = path_attr {
+            let path = self.dir.join(rel);
+            if path.exists() {
+                self.source_candidates.push(SourceFileDesc::new(
+                    path,
+                    SourceFileType::RustSource(ModType::Adjacent),
+                    None,
+                ));
+            } else {
+                self.push_missing(path);
+            }
+            return;
+        }
+
+        let (adjacent, mod_rs) = mod_candidates(&self.dir, name);
+        if adjacent.exists() {
+            self.source_candidates.push(SourceFileDesc::new(
+                adjacent,
+                SourceFileType::RustSource(ModType::Adjacent),
+                None,
+            ));
+        } else if mod_rs.exists() {
+            self.source_candidates.push(SourceFileDesc::new(
+                mod_rs,
+                SourceFileType::RustSource(ModType::ModRs),
+                None,
+            ));
+        } else {
+            self.push_missing(adjacent);
+            self.push_missing(mod_rs);
+        }
+    }
+
+    fn path_attr(attrs: &[Attribute]) -> Option<PathBuf> {
+        attrs.iter().find_map(|attr| {
+            if attr.path().is_ident("path") {
+                match &attr.meta {
+                    Meta::NameValue(nv) => match &nv.value {
+                        syn::Expr::Lit(lit) => match &lit.lit {
+                            syn::Lit::Str(s) => Some(PathBuf::from(s.value())),
+                            _ => None,
+                        },
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            } else {
+                None
+            }
+        })
+    }
+
+    fn visit_items(&mut self, items: &[Item]) {
+        for item in items {
+            self.visit_item(item);
+        }
+    }
+
+    fn visit_item(&mut self, item: &Item) {
+        match item {
+            Item::Mod(item_mod) => self.visit_mod_item(item_mod),
+            Item::Macro(item_macro) if !self.is_definitely_disabled(&item_macro.attrs) => {
+                self.visit_macro_path(&item_macro.mac);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_mod_item(&mut self, item: &ItemMod) {
+        if self.is_definitely_disabled(&item.attrs) {
+            return;
+        }
+
+        let name = item.ident.to_string();
+        match &item.content {
+            Some((_, items)) => {
+                // Inline module: no file of its own, but its children resolve
+                // under a subdirectory named after it.
+                let mut inner = self.child(self.dir.join(&name));
+                inner.visit_items(items);
+                self.source_candidates.append(&mut inner.source_candidates);
+                self.unresolved_items.append(&mut inner.unresolved_items);
+            }
+            None => {
+                let path_attr = Self::path_attr(&item.attrs);
+                self.resolve_mod(&name, path_attr);
+            }
+        }
+    }
+
+    fn visit_macro_path(&mut self, mac: &syn::Macro) {
+        if mac.path.is_ident("include") {
+            if let Ok(lit) = mac.parse_body::<syn::LitStr>() {
+                let path = self.dir.join(lit.value());
+                self.source_candidates.push(SourceFileDesc::new(
+                    path,
+                    SourceFileType::RustSnippet(self.dir.clone()),
+                    None,
+                ));
+            }
+        } else if mac.path.is_ident("include_bytes") {
+            if let Ok(lit) = mac.parse_body::<syn::LitStr>() {
+                self.source_candidates.push(SourceFileDesc::new(
+                    self.dir.join(lit.value()),
+                    SourceFileType::Bytes,
+                    None,
+                ));
+            }
+        } else if mac.path.is_ident("include_str") {
+            if let Ok(lit) = mac.parse_body::<syn::LitStr>() {
+                self.source_candidates.push(SourceFileDesc::new(
+                    self.dir.join(lit.value()),
+                    SourceFileType::String,
+                    None,
+                ));
+            }
+        } else if mac.path.segments.last().is_some_and(|seg| seg.ident == "cfg_if") {
+            self.visit_cfg_if(mac);
+        }
+    }
+
+    fn visit_cfg_if(&mut self, mac: &syn::Macro) {
+        let cfg_if = match syn::parse2::<CfgIf>(mac.tokens.clone()) {
+            Ok(cfg_if) => cfg_if,
+            Err(_) => return,
+        };
+
+        let cfg = match &self.cfg {
+            // With nothing known about the target `cfg`, we can't tell which
+            // branch would actually get compiled, so keep everything --
+            // matching this crate's default "include everything" behavior.
+            None => {
+                for branch in &cfg_if.branches {
+                    self.visit_items(&branch.items);
+                }
+                return;
+            }
+            Some(cfg) => cfg,
+        };
+
+        // `cfg_if!` lowers to whichever branch's predicate is true, checked
+        // in order -- mirror that by taking the first branch we can't prove
+        // is disabled (an `Unknown` predicate is kept, same as anywhere
+        // else).
+        let selected = cfg_if.branches.into_iter().find(|branch| match &branch.predicate {
+            Some(meta) => !cfg.eval(meta).is_definitely_false(),
+            None => true,
+        });
+
+        if let Some(branch) = selected {
+            self.visit_items(&branch.items);
+        }
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for SourceFinder {
+    fn visit_item_mod(&mut self, item: &'ast ItemMod) {
+        self.visit_mod_item(item);
+    }
+
+    fn visit_item_macro(&mut self, item: &'ast syn::ItemMacro) {
+        if self.is_definitely_disabled(&item.attrs) {
+            return;
+        }
+        syn::visit::visit_item_macro(self, item);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.visit_macro_path(mac);
+        syn::visit::visit_macro(self, mac);
+    }
+}
+
+/// One `if #[cfg(...)] { .. }` / `else if #[cfg(...)] { .. }` / `else { .. }`
+/// branch of a `cfg_if!` invocation.
+struct CfgIfBranch {
+    /// `None` for a trailing `else` with no `cfg`.
+    predicate: Option<Meta>,
+    items: Vec<Item>,
+}
+
+/// A parsed `cfg_if! { .. }` body.
+struct CfgIf {
+    branches: Vec<CfgIfBranch>,
+}
+
+fn branch_predicate(attrs: &[Attribute]) -> Option<Meta> {
+    attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("cfg"))
+        .and_then(|attr| match &attr.meta {
+            Meta::List(list) => list.parse_args::<Meta>().ok(),
+            _ => None,
+        })
+}
+
+fn parse_branch_items(input: ParseStream) -> syn::Result<Vec<Item>> {
+    let content;
+    syn::braced!(content in input);
+    let mut items = vec![];
+    while !content.is_empty() {
+        items.push(content.parse()?);
+    }
+    Ok(items)
+}
+
+impl Parse for CfgIf {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut branches = vec![];
+        loop {
+            input.parse::<syn::Token![if]>()?;
+            let attrs = input.call(Attribute::parse_outer)?;
+            let predicate = branch_predicate(&attrs);
+            let items = parse_branch_items(input)?;
+            branches.push(CfgIfBranch { predicate, items });
+
+            if !input.peek(syn::Token![else]) {
+                break;
+            }
+            input.parse::<syn::Token![else]>()?;
+            if input.peek(syn::Token![if]) {
+                continue;
+            }
+            let items = parse_branch_items(input)?;
+            branches.push(CfgIfBranch {
+                predicate: None,
+                items,
+            });
+            break;
+        }
+        Ok(CfgIf { branches })
+    }
+}
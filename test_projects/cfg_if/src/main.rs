@@ -0,0 +1,17 @@
+cfg_if::cfg_if! {
+    if #[cfg(unix)] {
+        mod unix_mod;
+    } else if #[cfg(windows)] {
+        mod windows_mod;
+    } else {
+        mod other_mod;
+    }
+}
+
+#[cfg(not(unix))]
+mod not_unix_mod;
+
+#[cfg(not(unix))]
+include!("not_unix_snippet.rs");
+
+fn main() {}
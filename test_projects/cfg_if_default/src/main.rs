@@ -0,0 +1,11 @@
+cfg_if::cfg_if! {
+    if #[cfg(unix)] {
+        mod unix_mod;
+    } else if #[cfg(windows)] {
+        mod windows_mod;
+    } else {
+        mod other_mod;
+    }
+}
+
+fn main() {}
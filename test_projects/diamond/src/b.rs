@@ -0,0 +1,2 @@
+#[path = "../a/shared.rs"]
+mod shared;
@@ -1,7 +1,5 @@
-use srcfiles;
-
-use srcfiles::{error::Error, SourceFileDesc};
-use std::path::PathBuf;
+use srcfiles::{CfgSet, Error, SourceFileDesc};
+use std::path::{Path, PathBuf};
 
 fn assert_has_source(srcfiles: &[SourceFileDesc], path: &str) {
     assert!(
@@ -24,7 +22,7 @@ fn assert_missing_files(errors: &[(SourceFileDesc, Error)], path: &str) {
             } else {
                 None
             })
-            .any(|desc| desc.path == PathBuf::from(path)),
+            .any(|desc| desc.path == Path::new(path)),
         "No missing file with path {}",
         path
     );
@@ -82,3 +80,95 @@ fn inline_mods_test() {
     assert_has_source(&srcfiles, "test_projects/inline/src/a/c/d/mod.rs");
     assert_has_source(&srcfiles, "test_projects/inline/src/a/c/e/e/e.rs");
 }
+
+#[test]
+fn cfg_if_test() {
+    let mut cfg = CfgSet::new();
+    cfg.set_atom("unix");
+
+    let srcfiles = srcfiles::crate_srcfiles_with_cfg(
+        PathBuf::from("test_projects/cfg_if/src/main.rs"),
+        Some(cfg),
+    )
+    .unwrap();
+
+    // The `cfg_if!` picks its `#[cfg(unix)]` branch, and the `#[cfg(not(unix))]`
+    // mod and include! right after it are both pruned.
+    assert_eq!(srcfiles.len(), 2);
+    assert_has_source(&srcfiles, "test_projects/cfg_if/src/main.rs");
+    assert_has_source(&srcfiles, "test_projects/cfg_if/src/unix_mod.rs");
+}
+
+#[test]
+fn cfg_if_default_test() {
+    // With no `CfgSet`, no branch's predicate can be proven false, so every
+    // branch of the `cfg_if!` is kept -- matching plain `crate_srcfiles`'s
+    // "include everything" default.
+    let srcfiles = srcfiles::crate_srcfiles(PathBuf::from(
+        "test_projects/cfg_if_default/src/main.rs",
+    ))
+    .unwrap();
+
+    assert_eq!(srcfiles.len(), 4);
+    assert_has_source(&srcfiles, "test_projects/cfg_if_default/src/main.rs");
+    assert_has_source(&srcfiles, "test_projects/cfg_if_default/src/unix_mod.rs");
+    assert_has_source(&srcfiles, "test_projects/cfg_if_default/src/windows_mod.rs");
+    assert_has_source(&srcfiles, "test_projects/cfg_if_default/src/other_mod.rs");
+}
+
+#[test]
+fn cycle_test() {
+    let result =
+        srcfiles::crate_srcfiles(PathBuf::from("test_projects/cycle/src/main.rs")).unwrap_err();
+    let (srcfiles, errors) = (result.get_sources(), result.into_errors());
+
+    assert_has_source(&srcfiles, "test_projects/cycle/src/main.rs");
+    assert_has_source(&srcfiles, "test_projects/cycle/src/a.rs");
+    assert_has_source(&srcfiles, "test_projects/cycle/src/a/b.rs");
+
+    assert!(
+        errors
+            .iter()
+            .any(|error| matches!(&error.1, Error::CircularInclude { .. })),
+        "expected a CircularInclude error, got {errors:?}"
+    );
+}
+
+#[test]
+fn diamond_dedupe_test() {
+    let result =
+        srcfiles::crate_srcfiles(PathBuf::from("test_projects/diamond/src/main.rs")).unwrap_err();
+    let (srcfiles, errors) = (result.get_sources(), result.into_errors());
+
+    assert_has_source(&srcfiles, "test_projects/diamond/src/main.rs");
+    assert_has_source(&srcfiles, "test_projects/diamond/src/b.rs");
+    assert_has_source(&srcfiles, "test_projects/diamond/src/a.rs");
+    assert_has_source(&srcfiles, "test_projects/diamond/src/a/shared.rs");
+
+    // `shared.rs` is reachable both through `a.rs` (which cycles back to
+    // itself through `shared.rs`) and through `b.rs` (which doesn't).
+    // Deduping by canonical path must keep the `CircularInclude` error the
+    // `a` route found rather than silently dropping it just because the `b`
+    // route happened to reach `shared.rs` first.
+    let shared_canon = PathBuf::from("test_projects/diamond/src/a/shared.rs")
+        .canonicalize()
+        .unwrap();
+    assert!(
+        errors.iter().any(|(desc, err)| desc.path.canonicalize().unwrap() == shared_canon
+            && matches!(err, Error::CircularInclude { .. })),
+        "expected a CircularInclude on the shared.rs entry, got {errors:?}"
+    );
+}
+
+#[test]
+fn manifest_nondefault_layout_test() {
+    let results = srcfiles::manifest_srcfiles(PathBuf::from("test_projects/manifest")).unwrap();
+
+    let lib_sources = results
+        .get("manifest_fixture (lib)")
+        .expect("no lib target found");
+    assert_has_source(lib_sources, "test_projects/manifest/src/custom_lib.rs");
+
+    let bin_sources = results.get("tool (bin)").expect("no bin target found");
+    assert_has_source(bin_sources, "test_projects/manifest/tools/tool_main.rs");
+}